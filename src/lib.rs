@@ -7,6 +7,25 @@
 //! The crucial field offset calculation was extracted from the `memoffset` crate.
 //! Kudos to Gilad Naaman and Ralf Jung and all the other contributors.
 //!
+//! Each struct entry lists zero or more layout assertions:
+//!
+//! - `field: offset` checks a named field's byte offset, walking through
+//!   any nested `.field` or `[index]` segments, e.g. `inner.x: 4` or
+//!   `items[2]: 16`.
+//! - `from..=to: range` checks the byte range spanned by `from` through
+//!   `to` inclusive, the same quantity `memoffset`'s `span_of!` computes.
+//! - a bare integer entry, e.g. `0: offset`, checks a tuple or
+//!   tuple-struct field by index instead of by name.
+//!
+//! Every offset assertion also panics if the computed offset falls
+//! outside `size_of::<$name>()`, so a path that resolves to an
+//! impossibly large offset fails with a clear message instead of
+//! silently passing.
+//!
+//! A leading `mod prefix;` wraps the generated tests in a module of that
+//! name, so multiple `testaso!` invocations for colliding struct names can
+//! coexist in the same enclosing module.
+//!
 //! # Examples
 //! ```
 //! #[repr(C)]
@@ -22,18 +41,35 @@
 //!     b: [u8; 2],
 //!     c: i64,
 //! }
+//!
+//! #[repr(C)]
+//! struct Inner {
+//!     x: u8,
+//!     y: u32,
+//! }
+//!
+//! #[repr(C)]
+//! struct Outer {
+//!     tag: u32,
+//!     inner: Inner,
+//!     items: [u16; 4],
+//! }
+//!
+//! #[repr(C)]
+//! struct Color(u8, u8, u8, u8);
+//!
 //! #[cfg(test)]
 //! mod test {
 //!     use testaso::testaso;
 //!
-//!     use super::Simple;
-//!     use super::SimplePacked;
+//!     use super::{Color, Outer, Simple, SimplePacked};
 //!
 //!     testaso! {
 //!         struct Simple: 8, 16 => {
 //!             a: 0,
 //!             b: 4,
-//!             c: 8
+//!             c: 8,
+//!             a..=c: 0..16
 //!         }
 //!
 //!         struct SimplePacked: 1, 14 => {
@@ -41,6 +77,39 @@
 //!             b: 4,
 //!             c: 6
 //!         }
+//!
+//!         struct Outer: 4, 20 => {
+//!             tag: 0,
+//!             inner: 4,
+//!             inner.x: 4,
+//!             inner.y: 8,
+//!             items: 12,
+//!             items[0]: 12,
+//!             items[3]: 18
+//!         }
+//!
+//!         struct Color: 1, 4 => {
+//!             0: 0,
+//!             1: 1,
+//!             2: 2,
+//!             3: 3
+//!         }
+//!     }
+//! }
+//! ```
+//!
+//! Two otherwise-colliding invocations disambiguated with a name prefix:
+//! ```
+//! #[repr(C)]
+//! struct Simple {
+//!     a: u32,
+//! }
+//!
+//! testaso::testaso! {
+//!     mod ffi_v1;
+//!
+//!     struct Simple: 4, 4 => {
+//!         a: 0
 //!     }
 //! }
 //! ```
@@ -49,6 +118,25 @@
 
 /// Macro to test alignment, size and offsets of structs
 ///
+/// Each struct entry lists zero or more layout assertions:
+///
+/// - `field: offset` checks a named field's byte offset, walking through
+///   any nested `.field` or `[index]` segments, e.g. `inner.x: 4` or
+///   `items[2]: 16`.
+/// - `from..=to: range` checks the byte range spanned by `from` through
+///   `to` inclusive, the same quantity `memoffset`'s `span_of!` computes.
+/// - a bare integer entry, e.g. `0: offset`, checks a tuple or
+///   tuple-struct field by index instead of by name.
+///
+/// Every offset assertion also panics if the computed offset falls
+/// outside `size_of::<$name>()`, so a path that resolves to an
+/// impossibly large offset fails with a clear message instead of
+/// silently passing.
+///
+/// A leading `mod prefix;` wraps the generated tests in a module of that
+/// name, so multiple `testaso!` invocations for colliding struct names can
+/// coexist in the same enclosing module.
+///
 /// # Examples
 /// ```
 /// #[repr(C)]
@@ -65,18 +153,34 @@
 ///     c: i64,
 /// }
 ///
+/// #[repr(C)]
+/// struct Inner {
+///     x: u8,
+///     y: u32,
+/// }
+///
+/// #[repr(C)]
+/// struct Outer {
+///     tag: u32,
+///     inner: Inner,
+///     items: [u16; 4],
+/// }
+///
+/// #[repr(C)]
+/// struct Color(u8, u8, u8, u8);
+///
 /// #[cfg(test)]
 /// mod test {
 ///     use testaso::testaso;
 ///
-///     use super::Simple;
-///     use super::SimplePacked;
+///     use super::{Color, Outer, Simple, SimplePacked};
 ///
 ///     testaso! {
 ///         struct Simple: 8, 16 => {
 ///             a: 0,
 ///             b: 4,
-///             c: 8
+///             c: 8,
+///             a..=c: 0..16
 ///         }
 ///
 ///         struct SimplePacked: 1, 14 => {
@@ -84,11 +188,57 @@
 ///             b: 4,
 ///             c: 6
 ///         }
+///
+///         struct Outer: 4, 20 => {
+///             tag: 0,
+///             inner: 4,
+///             inner.x: 4,
+///             inner.y: 8,
+///             items: 12,
+///             items[0]: 12,
+///             items[3]: 18
+///         }
+///
+///         struct Color: 1, 4 => {
+///             0: 0,
+///             1: 1,
+///             2: 2,
+///             3: 3
+///         }
 ///     }
 /// }
+/// ```
+///
+/// Two otherwise-colliding invocations disambiguated with a name prefix:
+/// ```
+/// #[repr(C)]
+/// struct Simple {
+///     a: u32,
+/// }
+///
+/// testaso::testaso! {
+///     mod ffi_v1;
+///
+///     struct Simple: 4, 4 => {
+///         a: 0
+///     }
+/// }
+/// ```
+// Used by the `@span` arm below to get a field's size from a pointer to it
+// without ever dereferencing that pointer, so it is safe to call even on a
+// pointer into uninitialized memory.
+#[doc(hidden)]
+pub fn __size_of_pointee<T>(_ptr: *const T) -> usize {
+    core::mem::size_of::<T>()
+}
+
 #[macro_export]
 macro_rules! testaso {
-    (@off $name:path=>$field:ident) => { {
+    // `$first` is the outermost field and `$seg` is the (possibly empty)
+    // remainder of the path, e.g. for `outer.inner.leaf` we get
+    // `$first = outer` and `$seg = . inner . leaf`, and for `items[3]` we
+    // get `$first = items` and `$seg = [3]`.
+    (@off $name:path=>$first:ident $($seg:tt)*) => { {
         // The following was extracted from the `memoffset` crate:
 
         // No UB here, and the pointer does not dangle, either.
@@ -98,23 +248,148 @@ macro_rules! testaso {
         let uninit = core::mem::MaybeUninit::<$name>::uninit();
         let base_ptr: *const $name = uninit.as_ptr();
 
-        // Make sure the field actually exists. This line ensures that a
-        // compile-time error is generated if $field is accessed through a
-        // Deref impl.
+        // Make sure the outermost field actually exists. This line ensures
+        // that a compile-time error is generated if $first is accessed
+        // through a Deref impl.
         #[allow(clippy::unneeded_field_pattern)]
-        let $name { $field: _, .. };
+        let $name { $first: _, .. };
 
-        // Get the field address.
+        // Get the field address, walking through any remaining `.field` or
+        // `[index]` segments one at a time.
         // SAFETY:
         // Crucially, we know that this will not trigger a deref coercion because
         // of the field check we did above.
-        // Also, the pointer does not dangle.
-        let field_ptr = unsafe { core::ptr::addr_of!((*base_ptr).$field) };
+        // Also, none of the intermediate pointers dangle, and `addr_of!`
+        // never forms a reference into the uninitialized memory.
+        //
+        // `$first` and `$seg` only ever expand to field names and index
+        // expressions supplied by the macro caller at the `testaso!` call
+        // site, never to arbitrary unsafe code smuggled in through a
+        // metavariable, so allow the lint that otherwise guards against
+        // that.
+        #[allow(clippy::macro_metavars_in_unsafe)]
+        let field_ptr = unsafe {
+            testaso!(@addr core::ptr::addr_of!((*base_ptr).$first) => $($seg)*)
+        };
+
+        // Bindgen's generated layout tests use the same `checked_sub` here:
+        // on any unexpected pointer ordering this panics cleanly instead of
+        // silently wrapping around to a huge `usize`.
+        let offset = (field_ptr as usize)
+            .checked_sub(base_ptr as usize)
+            .expect("field pointer precedes struct base pointer");
+
+        // `<=`, not `<`: a trailing zero-sized field (e.g. a C-style
+        // flexible array member) legitimately sits at offset
+        // `size_of::<$name>()`. This still catches the typo case this
+        // assertion exists for, an offset computation that lands past the
+        // end of the struct entirely.
+        assert!(
+            offset <= core::mem::size_of::<$name>(),
+            "offset {} for {} is not within size_of::<{}>() = {}",
+            offset,
+            stringify!($first $($seg)*),
+            stringify!($name),
+            core::mem::size_of::<$name>()
+        );
+
+        offset
+    }};
+
+    // Tt-muncher that walks the remaining path segments, chaining `addr_of!`
+    // through each one without ever dereferencing into a real reference.
+    (@addr $ptr:expr=>) => { $ptr };
 
-        (field_ptr as usize) - (base_ptr as usize)
+    (@addr $ptr:expr=> . $seg:ident $($rest:tt)*) => {
+        testaso!(@addr core::ptr::addr_of!((*$ptr).$seg) => $($rest)*)
+    };
+
+    (@addr $ptr:expr=> [$idx:expr] $($rest:tt)*) => {
+        testaso!(@addr core::ptr::addr_of!((*$ptr)[$idx]) => $($rest)*)
+    };
+
+    // Computes the byte range spanned by the fields from `$from` through
+    // `$to` (inclusive), the same quantity `memoffset`'s `span_of!` returns.
+    // The low bound is just `$from`'s offset; the high bound is `$to`'s
+    // offset plus its own size, which we get without ever forming a
+    // reference to uninitialized memory by diffing it against the offset
+    // of the field that immediately follows `$to`, or against the whole
+    // struct's size when `$to` is the last field.
+    (@span $name:path=>$from:ident..=$to:ident) => { {
+        let low = testaso!(@off $name=>$from);
+        let high = testaso!(@off $name=>$to) + {
+            let uninit = core::mem::MaybeUninit::<$name>::uninit();
+            let base_ptr: *const $name = uninit.as_ptr();
+
+            #[allow(clippy::unneeded_field_pattern)]
+            let $name { $to: _, .. };
+
+            // SAFETY: same reasoning as the `@off` arm above; `$to` is
+            // known to exist and the pointer does not dangle. `to_ptr` is
+            // never dereferenced: `__size_of_pointee` only reads its
+            // pointee's type, so this stays sound even though `to_ptr`
+            // points into uninitialized memory.
+            let to_ptr = unsafe { core::ptr::addr_of!((*base_ptr).$to) };
+            $crate::__size_of_pointee(to_ptr)
+        };
+
+        low..high
     }};
 
-    ($(struct $name:path: $align:expr, $size:expr => { $($field:ident: $offset:expr),* })+) => {
+    // Like `@off`, but for tuples and tuple structs, whose fields are
+    // addressed by integer index rather than by name. Mirrors memoffset's
+    // `offset_of_tuple!`.
+    (@tupoff $name:path=>$idx:tt) => { {
+        let uninit = core::mem::MaybeUninit::<$name>::uninit();
+        let base_ptr: *const $name = uninit.as_ptr();
+
+        // Make sure the tuple actually has this many fields. This line
+        // ensures that a compile-time error is generated if $idx is
+        // accessed through a Deref impl.
+        let $name(..);
+
+        // SAFETY: same reasoning as the `@off` arm above.
+        //
+        // `$idx` only ever expands to an integer literal supplied by the
+        // macro caller, so allow the lint that otherwise guards against
+        // caller-supplied unsafe code reaching a metavariable inside this
+        // block.
+        #[allow(clippy::macro_metavars_in_unsafe)]
+        let field_ptr = unsafe { core::ptr::addr_of!((*base_ptr).$idx) };
+
+        let offset = (field_ptr as usize)
+            .checked_sub(base_ptr as usize)
+            .expect("field pointer precedes struct base pointer");
+
+        // See the matching comment in the `@off` arm: `<=` accommodates a
+        // trailing zero-sized field sitting right at the end of the type.
+        assert!(
+            offset <= core::mem::size_of::<$name>(),
+            "offset {} for {}.{} is not within size_of::<{}>() = {}",
+            offset,
+            stringify!($name),
+            $idx,
+            stringify!($name),
+            core::mem::size_of::<$name>()
+        );
+
+        offset
+    }};
+
+    // A leading `mod $prefix;` wraps the generated `align`/`size`/`offsets`
+    // tests in a module of that name, so multiple `testaso!` invocations can
+    // coexist in the same enclosing module without a duplicate-definition
+    // error.
+    (mod $prefix:ident; $($rest:tt)+) => {
+        #[cfg(test)]
+        mod $prefix {
+            use super::*;
+
+            testaso!($($rest)+);
+        }
+    };
+
+    ($(struct $name:path: $align:expr, $size:expr => { $($entries:tt)* })+) => {
         #[cfg(test)]
         #[test]
         fn align() {
@@ -149,17 +424,115 @@ macro_rules! testaso {
         #[test]
         fn offsets() {
             $(
+                testaso!(@entries $name => $($entries)*);
+            )+
+        }
+    };
+
+    // Tt-muncher that walks a struct's field list one entry at a time,
+    // emitting either an offset or a span assertion for each, so both
+    // kinds of entry can be freely mixed in a single `testaso!` body.
+    (@entries $name:path=>) => {};
+
+    (@entries $name:path=> $from:ident..=$to:ident: $range:expr $(, $($rest:tt)*)?) => {
+        assert_eq!(
+            testaso!(@span $name=>$from..=$to),
+            $range,
+            "span: {}::{}..={}",
+            stringify!($name),
+            stringify!($from),
+            stringify!($to)
+        );
+        testaso!(@entries $name => $($($rest)*)?);
+    };
+
+    // A field path is the outermost field name, followed by any number of
+    // `.field` segments and an optional trailing `[index]`, e.g.
+    // `a`, `a.b.c` or `items[2]`.
+    (@entries $name:path=> $first:ident $(. $seg:ident)* $([$idx:expr])?: $offset:expr $(, $($rest:tt)*)?) => {
+        assert_eq!(
+            testaso!(@off $name=>$first $(. $seg)* $([$idx])?),
+            $offset,
+            "offset: {}::{}",
+            stringify!($name),
+            stringify!($first $(. $seg)* $([$idx])?)
+        );
+        testaso!(@entries $name => $($($rest)*)?);
+    };
+
+    // A bare integer designates a tuple or tuple-struct field, e.g.
+    // `struct Color: 4, 4 => { 0: 0, 1: 1, 2: 2 }`.
+    (@entries $name:path=> $idx:tt: $offset:expr $(, $($rest:tt)*)?) => {
+        assert_eq!(
+            testaso!(@tupoff $name=>$idx),
+            $offset,
+            "offset: {}.{}",
+            stringify!($name),
+            stringify!($idx)
+        );
+        testaso!(@entries $name => $($($rest)*)?);
+    };
+}
+
+/// Compile-time counterpart to [`testaso!`].
+///
+/// `align_of`, `size_of` and `core::mem::offset_of!` are all const-evaluable,
+/// so the layout checks this crate performs can be enforced at compile time
+/// instead of in a `#[cfg(test)]` function that never runs on `no_std`
+/// targets. Each struct entry expands to a `const _: () = { ... };` block
+/// guarded by `assert!`, so a layout regression fails the build rather than
+/// `cargo test`.
+///
+/// Only plain (possibly nested) named-field paths are supported, matching
+/// what `core::mem::offset_of!` itself accepts; spans, array indices and
+/// tuple fields are only available through [`testaso!`].
+///
+/// Requires the `offset_of` feature, which gates this crate's minimum
+/// supported Rust version on the release that stabilized built-in
+/// `offset_of!` (Rust 1.77).
+///
+/// # Examples
+/// ```
+/// # #[cfg(feature = "offset_of")]
+/// # {
+/// #[repr(C)]
+/// struct Simple {
+///     a: u32,
+///     b: [u8; 2],
+///     c: i64,
+/// }
+///
+/// testaso::testaso_const! {
+///     struct Simple: 8, 16 => {
+///         a: 0,
+///         b: 4,
+///         c: 8
+///     }
+/// }
+/// # }
+/// ```
+#[cfg(feature = "offset_of")]
+#[macro_export]
+macro_rules! testaso_const {
+    ($(struct $name:path: $align:expr, $size:expr => { $($field:ident $(. $seg:ident)*: $offset:expr),* $(,)? })+) => {
+        $(
+            const _: () = {
+                assert!(
+                    core::mem::align_of::<$name>() == $align,
+                    concat!("align: ", stringify!($name))
+                );
+                assert!(
+                    core::mem::size_of::<$name>() == $size,
+                    concat!("size: ", stringify!($name))
+                );
                 $(
-                    assert_eq!(
-                        testaso!(@off $name=>$field),
-                        $offset,
-                        "offset: {}::{}",
-                        stringify!($name),
-                        stringify!($field)
+                    assert!(
+                        core::mem::offset_of!($name, $field $(.$seg)*) == $offset,
+                        concat!("offset: ", stringify!($name), "::", stringify!($field $(.$seg)*))
                     );
                 )*
+            };
         )+
-        }
     };
 }
 
@@ -198,11 +571,46 @@ mod test {
         }
     }
 
+    #[repr(C)]
+    pub struct WithHole {
+        a: u8,
+        b: u32,
+        c: u8,
+    }
+
+    #[repr(C)]
+    pub struct Inner {
+        x: u8,
+        y: u32,
+    }
+
+    #[repr(C)]
+    pub struct Outer {
+        tag: u32,
+        inner: Inner,
+        items: [u16; 4],
+    }
+
+    #[repr(C)]
+    pub struct Color(u8, u8, u8, u8);
+
+    // C's flexible array member idiom: `data`'s offset legitimately equals
+    // `size_of::<FlexHeader>()`, since a zero-sized trailing array
+    // contributes no size of its own. Regression test for the offset
+    // bound check in `@off`/`@tupoff`, which must accept `offset ==
+    // size_of::<$name>()` and not just `offset < size_of::<$name>()`.
+    #[repr(C)]
+    pub struct FlexHeader {
+        len: u32,
+        data: [u8; 0],
+    }
+
     testaso! {
         struct Simple: 8, 16 => {
             a: 0,
             b: 4,
-            c: 8
+            c: 8,
+            a..=c: 0..16
         }
 
         struct SimplePacked: 1, 14 => {
@@ -224,5 +632,73 @@ mod test {
             a: 0,
             b: 8
         }
+
+        struct WithHole: 4, 12 => {
+            a: 0,
+            b: 4,
+            c: 8,
+            b..=c: 4..9
+        }
+
+        struct Outer: 4, 20 => {
+            tag: 0,
+            inner: 4,
+            inner.x: 4,
+            inner.y: 8,
+            items: 12,
+            items[0]: 12,
+            items[3]: 18
+        }
+
+        struct Color: 1, 4 => {
+            0: 0,
+            1: 1,
+            2: 2,
+            3: 3
+        }
+
+        struct FlexHeader: 4, 4 => {
+            len: 0,
+            data: 4
+        }
+    }
+
+    #[cfg(feature = "offset_of")]
+    crate::testaso_const! {
+        struct Simple: 8, 16 => {
+            a: 0,
+            b: 4,
+            c: 8
+        }
+
+        struct Outer: 4, 20 => {
+            tag: 0,
+            inner: 4,
+            inner.x: 4,
+            inner.y: 8,
+            items: 12
+        }
+    }
+
+    // Two otherwise-colliding invocations in the same module, disambiguated
+    // by a name prefix.
+    testaso! {
+        mod ffi_v1;
+
+        struct Simple: 8, 16 => {
+            a: 0,
+            b: 4,
+            c: 8
+        }
+    }
+
+    testaso! {
+        mod ffi_v2;
+
+        struct SimplePacked: 1, 14 => {
+            a: 0,
+            b: 4,
+            c: 6
+        }
     }
 }